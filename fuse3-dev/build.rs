@@ -1,14 +1,39 @@
 use std::path::PathBuf;
 
-const WRAPPERS: [(&'static str, &'static str); 2] = [("wrapper.h", "bindings.rs"), ("wrapper_low.h", "bindings_low.rs")];
+// Running `wrapper.h` and `wrapper_low.h` through bindgen separately means any C struct shared
+// between the two headers (`fuse_args`, `fuse_session`, ...) gets two independently-generated Rust
+// types. `src/lib.rs` assumes those pairs stay layout-compatible so it can punn pointers between
+// them; see the `const _` size/align assertions there, which fail the build if a libfuse header
+// change ever breaks that assumption.
+const WRAPPERS: [(&'static str, &'static str); 2] = [
+    ("wrapper.h", "bindings.rs"),
+    ("wrapper_low.h", "bindings_low.rs"),
+];
+
+/// libfuse 3.12 turned `struct fuse_loop_config` opaque behind `fuse_loop_cfg_create`/
+/// `fuse_loop_cfg_set_*`, while `Fuse::loop_multi` (src/lib.rs) still builds it as a plain struct
+/// literal from bindgen's generated layout. Linking against 3.12+ would silently diverge from the
+/// real ABI, so refuse to build rather than generate bindings that no longer match.
+fn check_loop_config_is_still_a_plain_struct(version: &str) {
+    let mut parts = version.split('.').filter_map(|p| p.parse::<u32>().ok());
+    let (Some(major), Some(minor)) = (parts.next(), parts.next()) else {
+        return;
+    };
+    assert!(
+        (major, minor) < (3, 12),
+        "fuse3 {version} is newer than this crate's loop_multi wrapper supports (>= 3.12 made \
+         fuse_loop_config opaque); update Fuse::loop_multi before bumping this bound"
+    );
+}
 
 fn main() {
     let lib = pkg_config::Config::new()
         .atleast_version("0.29.0")
         .probe("fuse3")
         .expect("Couldn't find library fuse3 on the system");
+    check_loop_config_is_still_a_plain_struct(&lib.version);
     let out_dir = PathBuf::from(std::env::var("OUT_DIR").expect("Unreachable: No OUT_DIR"));
-    WRAPPERS.iter().for_each(|(input,  output)| {
+    WRAPPERS.iter().for_each(|(input, output)| {
         bindgen::builder()
             .header(*input)
             .clang_args(
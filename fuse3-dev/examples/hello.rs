@@ -126,7 +126,10 @@ unsafe extern "C" fn hello_read(
 ) -> c_int {
     let path = CStr::from_ptr(path as *mut _);
     let path = path.to_string_lossy();
-    eprintln!("trace: hello_read {} (off: {}, size: {})", path, offset, size);
+    eprintln!(
+        "trace: hello_read {} (off: {}, size: {})",
+        path, offset, size
+    );
 
     if path[1..] != FUSE_OPTIONS.filename.display().to_string() {
         return -(Errno::ENOENT as i32);
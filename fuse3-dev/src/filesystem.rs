@@ -0,0 +1,278 @@
+use std::ffi::{c_void, CStr, CString};
+use std::os::raw::{c_char, c_int};
+use std::path::Path;
+use std::time::SystemTime;
+
+use nix::errno::Errno;
+
+use crate::raw;
+
+/// Subset of `struct stat` that a [`Filesystem`] needs to fill in for [`Filesystem::getattr`].
+#[derive(Debug, Clone, Copy)]
+pub struct FileAttr {
+    /// `st_mode`, including the file type bits (`S_IFREG`, `S_IFDIR`, ...).
+    pub mode: u32,
+    /// `st_nlink`.
+    pub nlink: u32,
+    /// `st_size`, in bytes.
+    pub size: u64,
+    /// `st_atime`.
+    pub atime: SystemTime,
+    /// `st_mtime`.
+    pub mtime: SystemTime,
+    /// `st_ctime`.
+    pub ctime: SystemTime,
+}
+
+impl Default for FileAttr {
+    fn default() -> Self {
+        Self {
+            mode: 0,
+            nlink: 1,
+            size: 0,
+            atime: SystemTime::UNIX_EPOCH,
+            mtime: SystemTime::UNIX_EPOCH,
+            ctime: SystemTime::UNIX_EPOCH,
+        }
+    }
+}
+
+/// A safe, object-oriented alternative to hand-writing a [`raw::fuse_operations`] table.
+///
+/// Every method has a default implementation returning [`Errno::ENOSYS`], so implementors only
+/// need to override the operations their filesystem actually supports. Use
+/// [`crate::Fuse::from_filesystem`] to turn an implementation into a mountable [`crate::Fuse`].
+pub trait Filesystem {
+    /// Called once the filesystem is mounted, before any other operation.
+    fn init(&self) -> Result<(), Errno> {
+        Err(Errno::ENOSYS)
+    }
+
+    /// Look up the attributes of `path`, equivalent to `stat(2)`.
+    fn getattr(&self, path: &Path) -> Result<FileAttr, Errno> {
+        let _ = path;
+        Err(Errno::ENOSYS)
+    }
+
+    /// List the entries of the directory at `path`, not including `.` and `..`.
+    fn readdir(&self, path: &Path) -> Result<Vec<String>, Errno> {
+        let _ = path;
+        Err(Errno::ENOSYS)
+    }
+
+    /// Check that `path` may be opened, equivalent to `open(2)`.
+    fn open(&self, path: &Path) -> Result<(), Errno> {
+        let _ = path;
+        Err(Errno::ENOSYS)
+    }
+
+    /// Read up to `size` bytes from `path` starting at `offset`.
+    fn read(&self, path: &Path, offset: i64, size: usize) -> Result<Vec<u8>, Errno> {
+        let _ = (path, offset, size);
+        Err(Errno::ENOSYS)
+    }
+
+    /// Write `data` to `path` starting at `offset`, returning the number of bytes written.
+    fn write(&self, path: &Path, offset: i64, data: &[u8]) -> Result<usize, Errno> {
+        let _ = (path, offset, data);
+        Err(Errno::ENOSYS)
+    }
+}
+
+/// Recovers the `T` stashed in the FUSE private-data pointer for the currently-running callback.
+///
+/// Safety: must only be called from within a `fuse_operations` callback invoked by libfuse for a
+/// `Fuse<T>` built from an operations table created by [`operations_table`].
+unsafe fn current_filesystem<'a, T>() -> Option<&'a T> {
+    let ctx = raw::fuse_get_context();
+    if ctx.is_null() {
+        return None;
+    }
+    ((*ctx).private_data as *const T).as_ref()
+}
+
+/// Converts a NUL-terminated FUSE path into a `&Path`.
+///
+/// Safety: `path` must be a valid, NUL-terminated C string for the duration of the call.
+unsafe fn path_from_raw<'a>(path: *const c_char) -> &'a Path {
+    Path::new(CStr::from_ptr(path).to_str().expect("non UTF-8 FUSE path"))
+}
+
+#[inline]
+fn errno_to_c_int(err: Errno) -> c_int {
+    -(err as i32)
+}
+
+/// Splits `time` into `(seconds, nanoseconds)` since the Unix epoch, saturating to zero for times
+/// before it, for filling in a `stat`'s `st_atim`/`st_mtim`/`st_ctim`.
+pub(crate) fn unix_time_parts(time: SystemTime) -> (i64, i64) {
+    match time.duration_since(SystemTime::UNIX_EPOCH) {
+        Ok(d) => (d.as_secs() as i64, d.subsec_nanos() as i64),
+        Err(_) => (0, 0),
+    }
+}
+
+/// Fills in `stbuf`'s `st_atim`/`st_mtim`/`st_ctim` from `attr`.
+///
+/// Safety: `stbuf` must point to a valid, writable `stat`.
+unsafe fn write_stat_times(stbuf: *mut raw::stat, attr: &FileAttr) {
+    let (atime_sec, atime_nsec) = unix_time_parts(attr.atime);
+    let (mtime_sec, mtime_nsec) = unix_time_parts(attr.mtime);
+    let (ctime_sec, ctime_nsec) = unix_time_parts(attr.ctime);
+    (*stbuf).st_atim.tv_sec = atime_sec as _;
+    (*stbuf).st_atim.tv_nsec = atime_nsec as _;
+    (*stbuf).st_mtim.tv_sec = mtime_sec as _;
+    (*stbuf).st_mtim.tv_nsec = mtime_nsec as _;
+    (*stbuf).st_ctim.tv_sec = ctime_sec as _;
+    (*stbuf).st_ctim.tv_nsec = ctime_nsec as _;
+}
+
+unsafe extern "C" fn init_trampoline<T: Filesystem>(
+    _conn: *mut raw::fuse_conn_info,
+    _cfg: *mut raw::fuse_config,
+) -> *mut c_void {
+    let ctx = raw::fuse_get_context();
+    if let Some(fs) = current_filesystem::<T>() {
+        let _ = fs.init();
+    }
+    if ctx.is_null() {
+        std::ptr::null_mut()
+    } else {
+        (*ctx).private_data
+    }
+}
+
+unsafe extern "C" fn getattr_trampoline<T: Filesystem>(
+    path: *const c_char,
+    stbuf: *mut raw::stat,
+    _fi: *mut raw::fuse_file_info,
+) -> c_int {
+    let Some(fs) = current_filesystem::<T>() else {
+        return errno_to_c_int(Errno::EIO);
+    };
+    match fs.getattr(path_from_raw(path)) {
+        Ok(attr) => {
+            (*stbuf).st_mode = attr.mode;
+            (*stbuf).st_nlink = attr.nlink as _;
+            (*stbuf).st_size = attr.size as _;
+            write_stat_times(stbuf, &attr);
+            0
+        }
+        Err(e) => errno_to_c_int(e),
+    }
+}
+
+unsafe extern "C" fn readdir_trampoline<T: Filesystem>(
+    path: *const c_char,
+    buf: *mut c_void,
+    filler: raw::fuse_fill_dir_t,
+    _offset: raw::off_t,
+    _fi: *mut raw::fuse_file_info,
+    _flags: raw::fuse_readdir_flags,
+) -> c_int {
+    let Some(filler) = filler else {
+        return errno_to_c_int(Errno::EIO);
+    };
+    let Some(fs) = current_filesystem::<T>() else {
+        return errno_to_c_int(Errno::EIO);
+    };
+    match fs.readdir(path_from_raw(path)) {
+        Ok(entries) => {
+            for name in [".", ".."].into_iter().map(String::from).chain(entries) {
+                let name = CString::new(name).expect("entry name must not contain NUL");
+                if filler(buf, name.as_ptr(), std::ptr::null_mut(), 0, 0) != 0 {
+                    break;
+                }
+            }
+            0
+        }
+        Err(e) => errno_to_c_int(e),
+    }
+}
+
+unsafe extern "C" fn open_trampoline<T: Filesystem>(
+    path: *const c_char,
+    _fi: *mut raw::fuse_file_info,
+) -> c_int {
+    let Some(fs) = current_filesystem::<T>() else {
+        return errno_to_c_int(Errno::EIO);
+    };
+    match fs.open(path_from_raw(path)) {
+        Ok(()) => 0,
+        Err(e) => errno_to_c_int(e),
+    }
+}
+
+unsafe extern "C" fn read_trampoline<T: Filesystem>(
+    path: *const c_char,
+    buf: *mut c_char,
+    size: raw::size_t,
+    offset: raw::off_t,
+    _fi: *mut raw::fuse_file_info,
+) -> c_int {
+    let Some(fs) = current_filesystem::<T>() else {
+        return errno_to_c_int(Errno::EIO);
+    };
+    match fs.read(path_from_raw(path), offset as i64, size as usize) {
+        Ok(data) => {
+            let len = data.len().min(size as usize);
+            std::ptr::copy_nonoverlapping(data.as_ptr(), buf as *mut u8, len);
+            len as c_int
+        }
+        Err(e) => errno_to_c_int(e),
+    }
+}
+
+unsafe extern "C" fn write_trampoline<T: Filesystem>(
+    path: *const c_char,
+    buf: *const c_char,
+    size: raw::size_t,
+    offset: raw::off_t,
+    _fi: *mut raw::fuse_file_info,
+) -> c_int {
+    let Some(fs) = current_filesystem::<T>() else {
+        return errno_to_c_int(Errno::EIO);
+    };
+    let data = std::slice::from_raw_parts(buf as *const u8, size as usize);
+    match fs.write(path_from_raw(path), offset as i64, data) {
+        Ok(written) => written as c_int,
+        Err(e) => errno_to_c_int(e),
+    }
+}
+
+/// Builds a [`raw::fuse_operations`] table of trampolines dispatching to a [`Filesystem`] impl.
+pub(crate) fn operations_table<T: Filesystem>() -> raw::fuse_operations {
+    raw::fuse_operations {
+        init: Some(init_trampoline::<T>),
+        getattr: Some(getattr_trampoline::<T>),
+        readdir: Some(readdir_trampoline::<T>),
+        open: Some(open_trampoline::<T>),
+        read: Some(read_trampoline::<T>),
+        write: Some(write_trampoline::<T>),
+        ..Default::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn unix_time_parts_splits_post_epoch_time() {
+        let time = SystemTime::UNIX_EPOCH + Duration::new(1_700_000_000, 123_456_789);
+        assert_eq!(unix_time_parts(time), (1_700_000_000, 123_456_789));
+    }
+
+    #[test]
+    fn unix_time_parts_handles_the_epoch_itself() {
+        assert_eq!(unix_time_parts(SystemTime::UNIX_EPOCH), (0, 0));
+    }
+
+    #[test]
+    fn unix_time_parts_saturates_pre_epoch_time_to_zero() {
+        let time = SystemTime::UNIX_EPOCH - Duration::from_secs(1);
+        assert_eq!(unix_time_parts(time), (0, 0));
+    }
+}
@@ -2,8 +2,9 @@ use std::borrow::{Borrow, BorrowMut};
 use std::ffi::{c_void, CStr, CString};
 use std::fmt::{Debug, Display, Formatter};
 use std::marker::PhantomData;
-use std::mem::size_of_val;
+use std::mem::{align_of, size_of, size_of_val};
 use std::ops::{Deref, DerefMut};
+use std::os::fd::{AsFd, AsRawFd, BorrowedFd, RawFd};
 use std::os::raw::{c_char, c_int, c_long};
 use std::path::Path;
 use std::pin::Pin;
@@ -13,6 +14,12 @@ pub use nix;
 use nix::errno::Errno;
 use nix::libc;
 
+mod filesystem;
+mod session;
+
+pub use filesystem::{FileAttr, Filesystem};
+pub use session::{Buf, Entry, LowlevelOps, Request, Session};
+
 #[allow(non_upper_case_globals)]
 #[allow(non_camel_case_types)]
 #[allow(non_snake_case)]
@@ -23,6 +30,19 @@ pub mod raw {
     include!(concat!(env!("OUT_DIR"), "/bindings.rs"));
 }
 
+/// `fuse_args` and `fuse_session` are each run through bindgen twice — once for the high-level
+/// `wrapper.h` and once for the low-level `wrapper_low.h` — so `raw::fuse_args`/`raw::low::fuse_args`
+/// and `raw::fuse_session`/`raw::low::fuse_session` are independently-generated Rust types standing
+/// in for the same C struct. `FuseArgs::as_low_args` and [`Fuse::process_next`] punn pointers
+/// between them on that assumption; these assertions turn a future libfuse header change that
+/// breaks it into a compile error instead of silent ABI drift.
+const _: () = {
+    assert!(size_of::<raw::fuse_args>() == size_of::<raw::low::fuse_args>());
+    assert!(align_of::<raw::fuse_args>() == align_of::<raw::low::fuse_args>());
+    assert!(size_of::<raw::fuse_session>() == size_of::<raw::low::fuse_session>());
+    assert!(align_of::<raw::fuse_session>() == align_of::<raw::low::fuse_session>());
+};
+
 pub struct FFIRef<'a, T: ?Sized> {
     __phantom: PhantomData<&'a T>,
     _value: *mut T,
@@ -181,21 +201,29 @@ impl Deref for FuseArgs {
 impl FuseArgs {
     /// Parse FUSE args from the given String iterator.
     ///
-    /// TODO: Make it configurable
+    /// Each argument is handed to `fuse_opt_add_arg`, which copies it into its own `malloc`'d
+    /// storage and grows `fuse_args.argv` accordingly; the returned `FuseArgs` owns that
+    /// allocation and frees it (via `fuse_opt_free_args`) on drop, so no argument ever leaks.
+    /// For typed mount options, prefer [`FuseArgsBuilder`].
     pub fn from_iter(iter: impl Iterator<Item = String>) -> nix::Result<Self> {
-        let argv = iter
-            .map(|s| CString::new(s).unwrap().into_boxed_c_str())
-            .map(|s| Box::leak(s).as_ptr() as *mut _)
-            .collect::<Vec<_>>()
-            .leak();
-
-        let argc = argv.len() as c_int;
         let mut fuse_args = raw::fuse_args {
-            argc,
-            argv: argv.as_mut_ptr(),
+            argc: 0,
+            argv: null_mut(),
             allocated: 0,
         };
-        Errno::result(unsafe { raw::fuse_opt_parse(&mut fuse_args, null_mut(), null(), None) })?;
+        let result = (|| {
+            for arg in iter {
+                let arg = CString::new(arg).unwrap();
+                Errno::result(unsafe { raw::fuse_opt_add_arg(&mut fuse_args, arg.as_ptr()) })?;
+            }
+            Errno::result(unsafe { raw::fuse_opt_parse(&mut fuse_args, null_mut(), null(), None) })
+        })();
+        // On failure, `fuse_args` may already own a partially-built argv; free it here since it
+        // never makes it into the destructor-carrying `FFIBox` below.
+        if let Err(e) = result {
+            unsafe { raw::fuse_opt_free_args(&mut fuse_args) };
+            return Err(e);
+        }
         Ok(Self {
             _value: FFIBox::new(fuse_args, raw::fuse_opt_free_args),
         })
@@ -226,6 +254,118 @@ impl FuseArgs {
     }
 }
 
+/// Typed builder for the common `-o` mount options, in place of hand-assembling a `Vec<String>`.
+///
+/// Unrecognized or less common options can still be passed via [`FuseArgsBuilder::raw_option`].
+#[derive(Debug, Clone, Default)]
+pub struct FuseArgsBuilder {
+    allow_other: bool,
+    allow_root: bool,
+    default_permissions: bool,
+    auto_unmount: bool,
+    fsname: Option<String>,
+    subtype: Option<String>,
+    max_read: Option<u32>,
+    raw_options: Vec<String>,
+}
+
+impl FuseArgsBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Allows users other than the one who mounted the filesystem to access it.
+    pub fn allow_other(mut self, value: bool) -> Self {
+        self.allow_other = value;
+        self
+    }
+
+    /// Allows root to access the filesystem, even with `allow_other` unset.
+    pub fn allow_root(mut self, value: bool) -> Self {
+        self.allow_root = value;
+        self
+    }
+
+    /// Enables kernel-side permission checks instead of deferring every access to the
+    /// filesystem's `getattr`/`access` implementation.
+    pub fn default_permissions(mut self, value: bool) -> Self {
+        self.default_permissions = value;
+        self
+    }
+
+    /// Automatically unmounts the filesystem when the owning process exits.
+    pub fn auto_unmount(mut self, value: bool) -> Self {
+        self.auto_unmount = value;
+        self
+    }
+
+    /// Sets the filesystem name shown in `mount`/`df` output.
+    pub fn fsname(mut self, value: impl Into<String>) -> Self {
+        self.fsname = Some(value.into());
+        self
+    }
+
+    /// Sets the filesystem subtype shown in `mount`/`df` output.
+    pub fn subtype(mut self, value: impl Into<String>) -> Self {
+        self.subtype = Some(value.into());
+        self
+    }
+
+    /// Caps the size of a single `read` request.
+    pub fn max_read(mut self, value: u32) -> Self {
+        self.max_read = Some(value);
+        self
+    }
+
+    /// Appends a raw `-o` option not otherwise covered by a typed setter.
+    pub fn raw_option(mut self, value: impl Into<String>) -> Self {
+        self.raw_options.push(value.into());
+        self
+    }
+
+    fn option_strings(&self) -> Vec<String> {
+        let mut options = Vec::new();
+        if self.allow_other {
+            options.push("allow_other".to_string());
+        }
+        if self.allow_root {
+            options.push("allow_root".to_string());
+        }
+        if self.default_permissions {
+            options.push("default_permissions".to_string());
+        }
+        if self.auto_unmount {
+            options.push("auto_unmount".to_string());
+        }
+        if let Some(fsname) = &self.fsname {
+            options.push(format!("fsname={fsname}"));
+        }
+        if let Some(subtype) = &self.subtype {
+            options.push(format!("subtype={subtype}"));
+        }
+        if let Some(max_read) = self.max_read {
+            options.push(format!("max_read={max_read}"));
+        }
+        options.extend(self.raw_options.iter().cloned());
+        options
+    }
+
+    /// Assembles the configured options into a [`FuseArgs`], ready to hand to [`Fuse::new`].
+    pub fn build(&self) -> nix::Result<FuseArgs> {
+        let program = std::env::current_exe()
+            .unwrap()
+            .to_string_lossy()
+            .to_string();
+        let options = self.option_strings();
+        let mut argv = vec![program];
+        if !options.is_empty() {
+            argv.push("-o".to_string());
+            argv.push(options.join(","));
+        }
+        FuseArgs::from_iter(argv.into_iter())
+    }
+}
+
 impl Debug for FuseArgs {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         let data = (0..self.argc)
@@ -245,6 +385,28 @@ impl Debug for FuseArgs {
     }
 }
 
+/// Configuration for [`Fuse::loop_multi`], mirroring `struct fuse_loop_config`.
+#[derive(Debug, Clone, Copy)]
+pub struct LoopConfig {
+    /// Whether each worker thread clones its own channel file descriptor, required on some
+    /// platforms to dispatch requests truly in parallel.
+    pub clone_fd: bool,
+    /// Maximum number of idle worker threads to keep around between requests.
+    pub max_idle_threads: u32,
+    /// Maximum number of worker threads, bounding how many requests may be dispatched at once.
+    pub max_threads: u32,
+}
+
+impl Default for LoopConfig {
+    fn default() -> Self {
+        Self {
+            clone_fd: false,
+            max_idle_threads: 10,
+            max_threads: 10,
+        }
+    }
+}
+
 /// Raw FUSE driver.
 pub struct Fuse<T: ?Sized> {
     _value: FFIBox<raw::fuse>,
@@ -303,7 +465,7 @@ impl<T: ?Sized> Fuse<T> {
                 size_of_val(operations) as _,
                 private_data
                     .as_mut()
-                    .map(|p| p as *mut _ as *mut c_void)
+                    .map(|p| p.as_mut() as *mut T as *mut c_void)
                     .unwrap_or(null_mut()),
             )
         };
@@ -332,4 +494,144 @@ impl<T: ?Sized> Fuse<T> {
             Ok(())
         }
     }
+
+    /// Returns the kernel channel file descriptor backing this mounted session.
+    ///
+    /// Registering it with an external event loop (`epoll`, `mio`, tokio's `AsyncFd`, ...) lets
+    /// callers drive requests themselves via [`Fuse::process_next`] instead of the blocking
+    /// [`Fuse::loop_single`]. The returned `BorrowedFd` is tied to `self`, so it cannot outlive the
+    /// session it was borrowed from.
+    ///
+    /// # Panics
+    ///
+    /// Panics if called before a successful [`Fuse::mount`], since `fuse_session_fd` does not
+    /// return a valid descriptor until then.
+    pub fn session_fd(&self) -> BorrowedFd<'_> {
+        let session = unsafe { raw::fuse_get_session(self._value.as_ptr() as *mut _) };
+        let fd = unsafe { raw::fuse_session_fd(session) };
+        assert!(
+            fd >= 0,
+            "Fuse::session_fd called before a successful mount (no channel fd yet)"
+        );
+        unsafe { BorrowedFd::borrow_raw(fd) }
+    }
+
+    /// Reads and dispatches exactly one request from the kernel channel.
+    ///
+    /// Unlike [`Fuse::loop_single`], this does not block waiting for the session to end; it
+    /// services a single ready request and returns, so it can be called from a reactor callback
+    /// once [`Fuse::session_fd`] has been reported readable.
+    ///
+    /// Returns `Err(Errno::ENODEV)` once the session has exited and there is nothing left to
+    /// service, mirroring [`Session::receive`](crate::Session::receive), so callers driving this
+    /// from a reactor can tell the filesystem was unmounted instead of spinning forever.
+    pub fn process_next(&self) -> Result<(), Errno> {
+        let session = unsafe { raw::fuse_get_session(self._value.as_ptr() as *mut _) }
+            as *mut raw::low::fuse_session;
+        let mut buf: raw::low::fuse_buf = unsafe { std::mem::zeroed() };
+        let r = unsafe { raw::low::fuse_session_receive_buf(session, &mut buf) };
+        if r < 0 {
+            return Err(Errno::from_i32(-r));
+        }
+        if r == 0 {
+            return Err(Errno::ENODEV);
+        }
+        unsafe { raw::low::fuse_session_process_buf(session, &buf) };
+        unsafe { libc::free(buf.mem) };
+        Ok(())
+    }
+}
+
+impl<T: ?Sized + Send + Sync> Fuse<T> {
+    /// Runs the dispatch loop across multiple worker threads, wrapping `fuse_loop_mt`.
+    ///
+    /// Unlike [`Fuse::loop_single`], requests may be dispatched concurrently from different
+    /// threads, which is why this is only available when the private data `T` (the
+    /// [`Filesystem`] implementation, for [`Fuse::from_filesystem`]) is `Send + Sync`. The
+    /// callbacks of a raw `fuse_operations` table passed to [`Fuse::new`] are not checked.
+    pub fn loop_multi(&self, config: LoopConfig) -> Result<(), Errno> {
+        let mut raw_config = raw::fuse_loop_config {
+            clone_fd: config.clone_fd as c_int,
+            max_idle_threads: config.max_idle_threads as _,
+            max_threads: config.max_threads as _,
+        };
+        let r = unsafe { raw::fuse_loop_mt(self._value.as_ptr() as *mut _, &mut raw_config) };
+        if r < 0 {
+            Err(Errno::from_i32(-r))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl<T: ?Sized> AsFd for Fuse<T> {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.session_fd()
+    }
+}
+
+impl<T: ?Sized> AsRawFd for Fuse<T> {
+    fn as_raw_fd(&self) -> RawFd {
+        self.session_fd().as_raw_fd()
+    }
+}
+
+impl<T: Filesystem> Fuse<T> {
+    /// Mounts a safe [`Filesystem`] implementation, generating the `fuse_operations` trampolines
+    /// needed to drive it under the hood.
+    ///
+    /// Returns `None` if an unknown argument is passed to `args`, mirroring [`Fuse::new`].
+    pub fn from_filesystem(args: &FuseArgs, filesystem: T) -> Option<Self> {
+        Self::new(
+            args,
+            &filesystem::operations_table::<T>(),
+            Box::new(filesystem),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FuseArgsBuilder;
+
+    #[test]
+    fn option_strings_omits_unset_options() {
+        assert!(FuseArgsBuilder::new().option_strings().is_empty());
+    }
+
+    #[test]
+    fn option_strings_covers_flags_and_values() {
+        let options = FuseArgsBuilder::new()
+            .allow_other(true)
+            .allow_root(true)
+            .default_permissions(true)
+            .auto_unmount(true)
+            .fsname("myfs")
+            .subtype("custom")
+            .max_read(8192)
+            .raw_option("noatime")
+            .option_strings();
+        assert_eq!(
+            options,
+            vec![
+                "allow_other",
+                "allow_root",
+                "default_permissions",
+                "auto_unmount",
+                "fsname=myfs",
+                "subtype=custom",
+                "max_read=8192",
+                "noatime",
+            ]
+        );
+    }
+
+    #[test]
+    fn option_strings_ignores_disabled_flags() {
+        let options = FuseArgsBuilder::new()
+            .allow_other(true)
+            .allow_other(false)
+            .option_strings();
+        assert!(options.is_empty());
+    }
 }
@@ -0,0 +1,321 @@
+use std::ffi::{c_void, CStr};
+use std::mem::size_of_val;
+use std::os::fd::{AsFd, AsRawFd, BorrowedFd, RawFd};
+use std::os::raw::{c_char, c_int};
+use std::path::Path;
+use std::ptr::null_mut;
+
+use nix::errno::Errno;
+
+use crate::raw::low;
+use crate::{FFIBox, FileAttr, FuseArgs};
+
+/// A request handle for a single [`LowlevelOps`] callback invocation.
+///
+/// Every request must be answered exactly once via one of the `reply_*` methods (or
+/// [`Request::reply_err`]); libfuse's low-level protocol requires a reply before it will hand the
+/// kernel anything else for the corresponding inode, so dropping a `Request` without replying
+/// will hang the filesystem.
+pub struct Request {
+    req: low::fuse_req_t,
+}
+
+/// Parameters for [`Request::reply_entry`], mirroring `struct fuse_entry_param`.
+#[derive(Debug, Clone, Copy)]
+pub struct Entry {
+    pub ino: u64,
+    pub generation: u64,
+    pub attr: FileAttr,
+    pub attr_timeout: f64,
+    pub entry_timeout: f64,
+}
+
+fn file_attr_to_stat(attr: &FileAttr) -> low::stat {
+    let mut stat: low::stat = unsafe { std::mem::zeroed() };
+    stat.st_mode = attr.mode;
+    stat.st_nlink = attr.nlink as _;
+    stat.st_size = attr.size as _;
+    let (atime_sec, atime_nsec) = crate::filesystem::unix_time_parts(attr.atime);
+    let (mtime_sec, mtime_nsec) = crate::filesystem::unix_time_parts(attr.mtime);
+    let (ctime_sec, ctime_nsec) = crate::filesystem::unix_time_parts(attr.ctime);
+    stat.st_atim.tv_sec = atime_sec as _;
+    stat.st_atim.tv_nsec = atime_nsec as _;
+    stat.st_mtim.tv_sec = mtime_sec as _;
+    stat.st_mtim.tv_nsec = mtime_nsec as _;
+    stat.st_ctim.tv_sec = ctime_sec as _;
+    stat.st_ctim.tv_nsec = ctime_nsec as _;
+    stat
+}
+
+impl Request {
+    /// Answers the request with an error, equivalent to `fuse_reply_err`.
+    pub fn reply_err(self, err: Errno) {
+        unsafe { low::fuse_reply_err(self.req, err as c_int) };
+    }
+
+    /// Answers a `getattr` request with the given attributes, equivalent to `fuse_reply_attr`.
+    pub fn reply_attr(self, attr: &FileAttr, timeout: f64) {
+        let stat = file_attr_to_stat(attr);
+        unsafe { low::fuse_reply_attr(self.req, &stat, timeout) };
+    }
+
+    /// Answers a `lookup` request with a resolved entry, equivalent to `fuse_reply_entry`.
+    pub fn reply_entry(self, entry: &Entry) {
+        let mut param: low::fuse_entry_param = unsafe { std::mem::zeroed() };
+        param.ino = entry.ino;
+        param.generation = entry.generation;
+        param.attr = file_attr_to_stat(&entry.attr);
+        param.attr_timeout = entry.attr_timeout;
+        param.entry_timeout = entry.entry_timeout;
+        unsafe { low::fuse_reply_entry(self.req, &param) };
+    }
+
+    /// Answers a `read`/`readdir` request with raw bytes, equivalent to `fuse_reply_buf`.
+    pub fn reply_buf(self, data: &[u8]) {
+        unsafe { low::fuse_reply_buf(self.req, data.as_ptr() as *const c_char, data.len()) };
+    }
+}
+
+/// Safe counterpart to `fuse_lowlevel_ops`, for filesystems that address entries by inode rather
+/// than by path.
+///
+/// Every method has a default implementation answering [`nix::errno::Errno::ENOSYS`] (or, for
+/// [`LowlevelOps::forget`], doing nothing), so implementors only override what they support.
+pub trait LowlevelOps {
+    /// Resolves `name` inside the directory `parent`, replying with the child's attributes.
+    fn lookup(&self, req: Request, parent: u64, name: &CStr) {
+        let _ = (parent, name);
+        req.reply_err(Errno::ENOSYS);
+    }
+
+    /// Looks up the attributes of inode `ino`.
+    fn getattr(&self, req: Request, ino: u64) {
+        let _ = ino;
+        req.reply_err(Errno::ENOSYS);
+    }
+
+    /// Reads up to `size` bytes from inode `ino` starting at `offset`.
+    fn read(&self, req: Request, ino: u64, size: usize, offset: i64) {
+        let _ = (ino, size, offset);
+        req.reply_err(Errno::ENOSYS);
+    }
+
+    /// Lists the directory entries of inode `ino`.
+    fn readdir(&self, req: Request, ino: u64, size: usize, offset: i64) {
+        let _ = (ino, size, offset);
+        req.reply_err(Errno::ENOSYS);
+    }
+
+    /// Tells the filesystem the kernel has dropped `nlookup` references to `ino`.
+    ///
+    /// Unlike the other operations, `forget` carries no reply of its own: libfuse still expects
+    /// the request to be acknowledged, which the generated trampoline does on the implementor's
+    /// behalf once this returns.
+    fn forget(&self, ino: u64, nlookup: u64) {
+        let _ = (ino, nlookup);
+    }
+}
+
+unsafe fn userdata<'a, T>(req: low::fuse_req_t) -> Option<&'a T> {
+    (low::fuse_req_userdata(req) as *const T).as_ref()
+}
+
+unsafe extern "C" fn lookup_trampoline<T: LowlevelOps>(
+    req: low::fuse_req_t,
+    parent: u64,
+    name: *const c_char,
+) {
+    let Some(ops) = userdata::<T>(req) else {
+        low::fuse_reply_err(req, Errno::EIO as c_int);
+        return;
+    };
+    ops.lookup(Request { req }, parent, CStr::from_ptr(name));
+}
+
+unsafe extern "C" fn getattr_trampoline<T: LowlevelOps>(
+    req: low::fuse_req_t,
+    ino: u64,
+    _fi: *mut low::fuse_file_info,
+) {
+    let Some(ops) = userdata::<T>(req) else {
+        low::fuse_reply_err(req, Errno::EIO as c_int);
+        return;
+    };
+    ops.getattr(Request { req }, ino);
+}
+
+unsafe extern "C" fn read_trampoline<T: LowlevelOps>(
+    req: low::fuse_req_t,
+    ino: u64,
+    size: low::size_t,
+    off: low::off_t,
+    _fi: *mut low::fuse_file_info,
+) {
+    let Some(ops) = userdata::<T>(req) else {
+        low::fuse_reply_err(req, Errno::EIO as c_int);
+        return;
+    };
+    ops.read(Request { req }, ino, size as usize, off as i64);
+}
+
+unsafe extern "C" fn readdir_trampoline<T: LowlevelOps>(
+    req: low::fuse_req_t,
+    ino: u64,
+    size: low::size_t,
+    off: low::off_t,
+    _fi: *mut low::fuse_file_info,
+) {
+    let Some(ops) = userdata::<T>(req) else {
+        low::fuse_reply_err(req, Errno::EIO as c_int);
+        return;
+    };
+    ops.readdir(Request { req }, ino, size as usize, off as i64);
+}
+
+unsafe extern "C" fn forget_trampoline<T: LowlevelOps>(
+    req: low::fuse_req_t,
+    ino: u64,
+    nlookup: u64,
+) {
+    if let Some(ops) = userdata::<T>(req) {
+        ops.forget(ino, nlookup);
+    }
+    low::fuse_reply_none(req);
+}
+
+fn operations_table<T: LowlevelOps>() -> low::fuse_lowlevel_ops {
+    low::fuse_lowlevel_ops {
+        lookup: Some(lookup_trampoline::<T>),
+        getattr: Some(getattr_trampoline::<T>),
+        read: Some(read_trampoline::<T>),
+        readdir: Some(readdir_trampoline::<T>),
+        forget: Some(forget_trampoline::<T>),
+        ..Default::default()
+    }
+}
+
+/// An owned receive buffer for a single low-level request, as read by [`Session::receive`].
+pub struct Buf {
+    inner: low::fuse_buf,
+}
+
+impl Drop for Buf {
+    fn drop(&mut self) {
+        if !self.inner.mem.is_null() {
+            unsafe { nix::libc::free(self.inner.mem) };
+        }
+    }
+}
+
+/// A mounted low-level FUSE session, addressing entries by inode rather than by path.
+///
+/// Pairs naturally with [`crate::Fuse::session_fd`]-style async dispatch: once mounted, drive it
+/// by alternating [`Session::receive`] and [`Session::process`] whenever [`Session::fd`] reports
+/// readable, instead of a blocking loop.
+pub struct Session<T: ?Sized> {
+    _value: FFIBox<low::fuse_session>,
+    private_data: Option<Box<T>>,
+}
+
+impl<T: LowlevelOps> Session<T> {
+    /// Creates a new low-level session driven by `ops`, without mounting it yet.
+    pub fn new(args: &FuseArgs, ops: T) -> Result<Self, Errno> {
+        let mut private_data = Some(Box::new(ops));
+        let table = operations_table::<T>();
+        let r = unsafe {
+            low::fuse_session_new(
+                args.as_low_args(),
+                &table,
+                size_of_val(&table),
+                private_data
+                    .as_mut()
+                    .map(|p| p.as_mut() as *mut T as *mut c_void)
+                    .unwrap_or(null_mut()),
+            )
+        };
+        if r.is_null() {
+            return Err(Errno::EIO);
+        }
+        Ok(Self {
+            // Safety: `fuse_session_new` returned a non-null, owned session. `fuse_session_destroy`
+            // frees the session itself, so `run_free` stays false as with `Fuse::new`.
+            _value: unsafe { FFIBox::create(r, false, low::fuse_session_destroy) },
+            private_data,
+        })
+    }
+}
+
+impl<T: ?Sized> Session<T> {
+    /// Mounts the session at `mountpoint`, equivalent to `fuse_session_mount`.
+    pub fn mount(&mut self, mountpoint: &Path) -> Result<(), Errno> {
+        let mountpoint = std::ffi::CString::new(mountpoint.display().to_string()).unwrap();
+        Errno::result(unsafe { low::fuse_session_mount(self._value.as_mut(), mountpoint.as_ptr()) })
+            .map(|_| ())
+    }
+
+    /// Unmounts the session, equivalent to `fuse_session_unmount`.
+    pub fn unmount(&mut self) {
+        unsafe { low::fuse_session_unmount(self._value.as_mut()) }
+    }
+
+    /// Returns the kernel channel file descriptor backing this session, for driving
+    /// [`Session::receive`]/[`Session::process`] from an external event loop instead of a
+    /// blocking read.
+    ///
+    /// # Panics
+    ///
+    /// Panics if called before a successful [`Session::mount`], since `fuse_session_fd` does not
+    /// return a valid descriptor until then.
+    pub fn fd(&self) -> BorrowedFd<'_> {
+        let fd = unsafe { low::fuse_session_fd(self._value.as_ptr() as *mut _) };
+        assert!(
+            fd >= 0,
+            "Session::fd called before a successful mount (no channel fd yet)"
+        );
+        unsafe { BorrowedFd::borrow_raw(fd) }
+    }
+
+    /// Reads one request off the kernel channel into a freshly allocated [`Buf`].
+    ///
+    /// The buffer is not reused across calls: each call allocates a new one, which is freed when
+    /// the returned [`Buf`] is dropped.
+    ///
+    /// Returns `Err(Errno::ENODEV)` once the session has exited and there is nothing left to
+    /// receive, so callers can loop `while let Ok(buf) = session.receive() { ... }`.
+    pub fn receive(&mut self) -> Result<Buf, Errno> {
+        let mut buf: low::fuse_buf = unsafe { std::mem::zeroed() };
+        let r = unsafe { low::fuse_session_receive_buf(self._value.as_mut(), &mut buf) };
+        if r < 0 {
+            return Err(Errno::from_i32(-r));
+        }
+        if r == 0 {
+            return Err(Errno::ENODEV);
+        }
+        Ok(Buf { inner: buf })
+    }
+
+    /// Dispatches a buffer previously read by [`Session::receive`] to the [`LowlevelOps`] impl.
+    pub fn process(&mut self, buf: Buf) {
+        unsafe { low::fuse_session_process_buf(self._value.as_mut(), &buf.inner) }
+    }
+}
+
+impl<T: ?Sized> AsFd for Session<T> {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.fd()
+    }
+}
+
+impl<T: ?Sized> AsRawFd for Session<T> {
+    fn as_raw_fd(&self) -> RawFd {
+        self.fd().as_raw_fd()
+    }
+}
+
+impl FuseArgs {
+    /// Reinterprets this high-level `fuse_args` as the low-level binding of the same C struct,
+    /// for passing to `raw::low` session functions.
+    pub(crate) fn as_low_args(&self) -> *mut low::fuse_args {
+        self._value.as_ptr() as *mut low::fuse_args
+    }
+}